@@ -0,0 +1,43 @@
+use std::ops::{Deref, DerefMut};
+
+/// Abstracts over the lock backend used by [`UseLock`](crate::use_lock::UseLock), so the hook
+/// offers the same `read`/`write`/`with`/`with_mut` surface whether the lock blocks or is
+/// driven through `async`/`await`.
+pub trait Lock: 'static {
+    /// The concrete `RwLock<T>` type backing this implementation.
+    type RwLock<T>;
+    /// The guard returned by a read lock.
+    type RwLockReadGuard<'a, T: 'a>: Deref<Target = T>;
+    /// The guard returned by a write lock.
+    type RwLockWriteGuard<'a, T: 'a>: DerefMut<Target = T>;
+
+    /// Construct a new lock wrapping `value`.
+    fn new_rwlock<T>(value: T) -> Self::RwLock<T>;
+}
+
+/// Backs [`UseLock`](crate::use_lock::UseLock) with a blocking [`std::sync::RwLock`].
+pub struct SyncLock;
+
+impl Lock for SyncLock {
+    type RwLock<T> = std::sync::RwLock<T>;
+    type RwLockReadGuard<'a, T: 'a> = std::sync::RwLockReadGuard<'a, T>;
+    type RwLockWriteGuard<'a, T: 'a> = std::sync::RwLockWriteGuard<'a, T>;
+
+    fn new_rwlock<T>(value: T) -> Self::RwLock<T> {
+        std::sync::RwLock::new(value)
+    }
+}
+
+/// Backs [`UseLock`](crate::use_lock::UseLock) with an [`async_std::sync::RwLock`], making
+/// `read`/`write` `async`.
+pub struct AsyncLock;
+
+impl Lock for AsyncLock {
+    type RwLock<T> = async_std::sync::RwLock<T>;
+    type RwLockReadGuard<'a, T: 'a> = async_std::sync::RwLockReadGuard<'a, T>;
+    type RwLockWriteGuard<'a, T: 'a> = async_std::sync::RwLockWriteGuard<'a, T>;
+
+    fn new_rwlock<T>(value: T) -> Self::RwLock<T> {
+        async_std::sync::RwLock::new(value)
+    }
+}
@@ -0,0 +1,14 @@
+//! Indirection over the `RwLock` backing the shared-lock machinery, so the `loom` feature can
+//! swap in `loom`'s mocked equivalent for model checking the subscriber logic (the generation
+//! counter, `consumers` mutation in `Drop`, and `notify_consumers`) under weak memory
+//! interleavings that `std`'s real primitive can't exercise in a test.
+//!
+//! `Arc` is deliberately left out of this indirection: `loom::sync::Arc` doesn't support
+//! unsizing coercions on stable, and `notify_any: Arc<dyn Fn(ScopeId)>` relies on one, so
+//! `notify_any` stays on `std::sync::Arc` even when the `loom` feature is enabled.
+
+#[cfg(not(feature = "loom"))]
+pub(crate) use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+#[cfg(feature = "loom")]
+pub(crate) use loom::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
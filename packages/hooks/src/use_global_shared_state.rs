@@ -0,0 +1,127 @@
+use crate::sync::RwLock;
+use crate::use_shared_lock::{ProvidedLock, SharedLockReadGuard, SharedLockWriteGuard};
+use crate::use_shared_state::ProvidedStateInner;
+use dioxus_core::{ScopeId, ScopeState};
+use once_cell::sync::OnceCell;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+/// A piece of state shared across the whole app, independent of the context tree.
+///
+/// Unlike [`crate::use_shared_lock::use_shared_lock`], this works even between sibling
+/// subtrees or from outside of components entirely (e.g. a background task), since it doesn't
+/// rely on an ancestor having called `use_shared_lock_provider`. Declare one as a `static`:
+///
+/// ```ignore
+/// static COUNT: SharedState<u32> = SharedState::new(|| 0);
+/// ```
+pub struct SharedState<T: 'static> {
+    // `init` is kept separate from `cell` (rather than captured in a closure stored alongside
+    // it) so this struct holds nothing but plain data - a closure capturing `init` can't coerce
+    // to the `fn() -> ProvidedLock<T>` a `Lazy<ProvidedLock<T>>` field would need, which is the
+    // only shape `Lazy::new` accepts in a `const fn`.
+    init: fn() -> T,
+    cell: OnceCell<ProvidedLock<T>>,
+}
+
+impl<T: 'static> SharedState<T> {
+    /// Declare a new global shared state. `init` runs once, the first time the state is used.
+    pub const fn new(init: fn() -> T) -> Self {
+        Self {
+            init,
+            cell: OnceCell::new(),
+        }
+    }
+
+    /// Get a handle to the underlying lock, lazily initializing it on first access.
+    fn lock(&self) -> &ProvidedLock<T> {
+        self.cell.get_or_init(|| {
+            Arc::new(RwLock::new(ProvidedStateInner {
+                value: (self.init)(),
+                notify_any: Arc::new(|_| {}),
+                consumers: HashSet::new(),
+                gen: 0,
+                subscribers: Vec::new(),
+            }))
+        })
+    }
+
+    /// Read the shared value
+    pub fn read(&self) -> SharedLockReadGuard<T> {
+        match self.lock().read() {
+            Ok(guard) => SharedLockReadGuard::from_guard(guard),
+            Err(message) => panic!(
+                "Reading the shared state failed: {}\n({:?})",
+                message, message
+            ),
+        }
+    }
+
+    /// Write the shared value, notifying every consumer and subscriber registered through
+    /// [`use_global_shared_state`] or [`Self::subscribe`].
+    pub fn write(&self) -> SharedLockWriteGuard<'_, T> {
+        match self.lock().write() {
+            Ok(mut guard) => {
+                guard.notify_consumers();
+                SharedLockWriteGuard::from_guard(guard, true)
+            }
+            Err(message) => panic!(
+                "Writing the shared state failed: {}\n({:?})",
+                message, message
+            ),
+        }
+    }
+
+    /// Register a callback to run whenever the shared value changes, from anywhere in the app.
+    ///
+    /// Returning `false` from `subscriber` drops the subscription.
+    ///
+    /// `subscriber` runs while the write lock is still held (right before it's released), so
+    /// it must not call [`Self::write`] on the same `SharedState` - doing so will deadlock.
+    pub fn subscribe(&self, subscriber: impl FnMut(&T) -> bool + Send + Sync + 'static) {
+        self.lock().write().unwrap().subscribers.push(Box::new(subscriber));
+    }
+}
+
+impl<T: Clone + 'static> SharedState<T> {
+    /// Get a clone of the current shared value, without holding a guard.
+    pub fn get(&self) -> T {
+        self.read().clone()
+    }
+}
+
+/// Subscribes the calling scope to a [`SharedState`] declared as a `static`, re-rendering it
+/// whenever the state is written.
+pub fn use_global_shared_state<T: 'static>(
+    cx: &ScopeState,
+    state: &'static SharedState<T>,
+) -> &'static SharedState<T> {
+    cx.use_hook(|| {
+        let scope_id = cx.scope_id();
+        let notify_any = cx.schedule_update_any();
+        {
+            let mut inner = state.lock().write().unwrap();
+            inner.consumers.insert(scope_id);
+            inner.notify_any = notify_any;
+        }
+        GlobalSharedStateOwner { state, scope_id }
+    });
+    state
+}
+
+/// Unsubscribes the owning scope from its [`SharedState`] when the component is unmounted.
+struct GlobalSharedStateOwner<T: 'static> {
+    state: &'static SharedState<T>,
+    scope_id: ScopeId,
+}
+
+impl<T: 'static> Drop for GlobalSharedStateOwner<T> {
+    fn drop(&mut self) {
+        self.state
+            .lock()
+            .write()
+            .unwrap()
+            .consumers
+            .remove(&self.scope_id);
+    }
+}
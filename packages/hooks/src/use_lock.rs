@@ -1,22 +1,39 @@
+use crate::lock::{AsyncLock, Lock, SyncLock};
 use dioxus_core::ScopeState;
-use std::sync::Arc;
-use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::sync::{Arc, TryLockError};
 
-pub fn use_lock<T: 'static>(cx: &ScopeState, initialize_rwlock: impl FnOnce() -> T) -> &UseLock<T> {
+/// The error returned by the non-panicking `try_read`/`try_write` on [`UseLock`].
+#[derive(Debug)]
+pub enum LockError {
+    /// The lock is currently held by another reader or writer and would have blocked.
+    WouldBlock,
+    /// A writer panicked while holding the lock, poisoning it.
+    ///
+    /// The data behind the lock is still intact; call [`UseLock::clear_poison`] to allow
+    /// future reads and writes to succeed again.
+    Poisoned,
+}
+
+pub fn use_lock<T: 'static, L: Lock>(
+    cx: &ScopeState,
+    initialize_rwlock: impl FnOnce() -> T,
+) -> &UseLock<T, L> {
     cx.use_hook(|| UseLock {
         update: cx.schedule_update(),
-        value: Arc::new(RwLock::new(initialize_rwlock())),
+        value: Arc::new(L::new_rwlock(initialize_rwlock())),
         gen: 0,
     })
 }
 
-pub struct UseLock<T> {
+/// A hook to a `RwLock`-backed value, generic over the [`Lock`] backend `L` so the same API
+/// works for a blocking [`SyncLock`] or an `async` [`AsyncLock`].
+pub struct UseLock<T, L: Lock = SyncLock> {
     update: Arc<dyn Fn()>,
-    value: Arc<RwLock<T>>,
+    value: Arc<L::RwLock<T>>,
     gen: usize,
 }
 
-impl<T> Clone for UseLock<T> {
+impl<T, L: Lock> Clone for UseLock<T, L> {
     fn clone(&self) -> Self {
         Self {
             update: self.update.clone(),
@@ -26,12 +43,28 @@ impl<T> Clone for UseLock<T> {
     }
 }
 
-impl<T> UseLock<T> {
-    pub fn read(&self) -> RwLockReadGuard<'_, T> {
+impl<T, L: Lock> UseLock<T, L> {
+    pub fn needs_update(&self) {
+        (self.update)();
+    }
+}
+
+impl<T, L: Lock> PartialEq for UseLock<T, L> {
+    fn eq(&self, other: &Self) -> bool {
+        if Arc::ptr_eq(&self.value, &other.value) {
+            self.gen == other.gen
+        } else {
+            false
+        }
+    }
+}
+
+impl<T> UseLock<T, SyncLock> {
+    pub fn read(&self) -> std::sync::RwLockReadGuard<'_, T> {
         self.value.read().unwrap()
     }
 
-    pub fn write(&self) -> RwLockWriteGuard<'_, T> {
+    pub fn write(&self) -> std::sync::RwLockWriteGuard<'_, T> {
         self.needs_update();
         self.value.write().unwrap()
     }
@@ -41,29 +74,102 @@ impl<T> UseLock<T> {
         self.needs_update();
     }
 
-    pub fn write_silent(&self) -> RwLockWriteGuard<'_, T> {
+    pub fn write_silent(&self) -> std::sync::RwLockWriteGuard<'_, T> {
         self.value.write().unwrap()
     }
 
+    /// Read the value without blocking, instead of panicking if it is contended or poisoned.
+    pub fn try_read(&self) -> Result<std::sync::RwLockReadGuard<'_, T>, LockError> {
+        match self.value.try_read() {
+            Ok(guard) => Ok(guard),
+            Err(TryLockError::WouldBlock) => Err(LockError::WouldBlock),
+            Err(TryLockError::Poisoned(_)) => Err(LockError::Poisoned),
+        }
+    }
+
+    /// Write the value without blocking, instead of panicking if it is contended or poisoned.
+    ///
+    /// Unlike [`Self::write`], this does not schedule a re-render if the lock could not be acquired.
+    pub fn try_write(&self) -> Result<std::sync::RwLockWriteGuard<'_, T>, LockError> {
+        match self.value.try_write() {
+            Ok(guard) => {
+                self.needs_update();
+                Ok(guard)
+            }
+            Err(TryLockError::WouldBlock) => Err(LockError::WouldBlock),
+            Err(TryLockError::Poisoned(_)) => Err(LockError::Poisoned),
+        }
+    }
+
+    /// Clear the poison flag left behind by a writer that panicked while holding the lock.
+    ///
+    /// The last-written value is untouched, so components can keep reading it once the flag
+    /// is cleared instead of panicking on every subsequent render.
+    pub fn clear_poison(&self) {
+        self.value.clear_poison();
+    }
+
     pub fn with<O>(&self, immutable_callback: impl FnOnce(&T) -> O) -> O {
-        immutable_callback(&*self.read())
+        immutable_callback(&self.read())
     }
 
     pub fn with_mut<O>(&self, mutable_callback: impl FnOnce(&mut T) -> O) -> O {
-        mutable_callback(&mut *self.write())
+        mutable_callback(&mut self.write())
     }
+}
 
-    pub fn needs_update(&self) {
-        (self.update)();
+impl<T> UseLock<T, AsyncLock> {
+    pub async fn read(&self) -> async_std::sync::RwLockReadGuard<'_, T> {
+        self.value.read().await
     }
-}
 
-impl<T> PartialEq for UseLock<T> {
-    fn eq(&self, other: &Self) -> bool {
-        if Arc::ptr_eq(&self.value, &other.value) {
-            self.gen == other.gen
-        } else {
-            false
+    pub async fn write(&self) -> async_std::sync::RwLockWriteGuard<'_, T> {
+        self.needs_update();
+        self.value.write().await
+    }
+
+    pub async fn set(&self, new: T) {
+        *self.value.write().await = new;
+        self.needs_update();
+    }
+
+    pub async fn write_silent(&self) -> async_std::sync::RwLockWriteGuard<'_, T> {
+        self.value.write().await
+    }
+
+    /// Read the value without waiting, instead of blocking if it is contended.
+    ///
+    /// `async_std`'s lock never poisons, so the only failure is [`LockError::WouldBlock`].
+    pub fn try_read(&self) -> Result<async_std::sync::RwLockReadGuard<'_, T>, LockError> {
+        self.value.try_read().ok_or(LockError::WouldBlock)
+    }
+
+    /// Write the value without waiting, instead of blocking if it is contended.
+    ///
+    /// Unlike [`Self::write`], this does not schedule a re-render if the lock could not be acquired.
+    pub fn try_write(&self) -> Result<async_std::sync::RwLockWriteGuard<'_, T>, LockError> {
+        match self.value.try_write() {
+            Some(guard) => {
+                self.needs_update();
+                Ok(guard)
+            }
+            None => Err(LockError::WouldBlock),
         }
     }
+
+    pub async fn with<O>(&self, immutable_callback: impl FnOnce(&T) -> O) -> O {
+        immutable_callback(&self.read().await)
+    }
+
+    pub async fn with_mut<O>(&self, mutable_callback: impl FnOnce(&mut T) -> O) -> O {
+        mutable_callback(&mut self.write().await)
+    }
+}
+
+/// Like [`use_lock`], but backed by an [`async_std::sync::RwLock`] so `read`/`write` are `async`.
+pub fn use_async_lock<T: 'static>(
+    cx: &ScopeState,
+    initialize_rwlock: impl FnOnce() -> T,
+) -> &UseLock<T, AsyncLock> {
+    use_lock::<T, AsyncLock>(cx, initialize_rwlock)
 }
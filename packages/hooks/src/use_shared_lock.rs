@@ -1,38 +1,150 @@
+use crate::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+use crate::use_lock::LockError;
 use crate::use_shared_state::ProvidedStateInner;
 use dioxus_core::{ScopeId, ScopeState};
+use std::collections::HashSet;
 use std::ops::{Deref, DerefMut};
-use std::sync::{Arc, RwLockReadGuard, RwLockWriteGuard};
-use std::{collections::HashSet, sync::RwLock};
+use std::ptr::NonNull;
+use std::sync::Arc;
+#[cfg(not(feature = "loom"))]
+use std::sync::TryLockError;
 
-type ProvidedLock<T> = Arc<RwLock<ProvidedStateInner<T>>>;
+pub(crate) type ProvidedLock<T> = Arc<RwLock<ProvidedStateInner<T>>>;
 
-pub struct SharedLockReadGuard<'a, T> {
-    guard: RwLockReadGuard<'a, ProvidedStateInner<T>>,
+/// A read guard over the shared value, or a projection of one of its fields via [`Self::map`].
+///
+/// `Orig` is the type the lock was originally created with; it stays fixed across `map` calls
+/// so the guard keeps holding the same lock no matter how many times `T` is projected.
+pub struct SharedLockReadGuard<'a, T, Orig = T> {
+    // Kept only to hold the lock for `'a`; `value` is what callers actually see.
+    guard: RwLockReadGuard<'a, ProvidedStateInner<Orig>>,
+    value: &'a T,
 }
 
-impl<'a, T> Deref for SharedLockReadGuard<'a, T> {
+impl<'a, T, Orig> Deref for SharedLockReadGuard<'a, T, Orig> {
     type Target = T;
 
     fn deref(&self) -> &Self::Target {
-        &self.guard.value
+        self.value
     }
 }
 
-pub struct SharedLockWriteGuard<'a, T> {
-    guard: RwLockWriteGuard<'a, ProvidedStateInner<T>>,
+impl<'a, T> SharedLockReadGuard<'a, T, T> {
+    /// Build a top-level (non-projected) guard from a freshly acquired lock guard.
+    pub(crate) fn from_guard(guard: RwLockReadGuard<'a, ProvidedStateInner<T>>) -> Self {
+        // SAFETY: `guard` is moved into the returned struct, which keeps the lock held for
+        // as long as `value` may be accessed through it.
+        let value: &'a T = unsafe { &*(&guard.value as *const T) };
+        Self { guard, value }
+    }
+}
+
+impl<'a, T, Orig> SharedLockReadGuard<'a, T, Orig> {
+    /// Project this guard onto a sub-field of `T`, keeping the lock held for the guard's lifetime.
+    ///
+    /// This lets a component subscribe to and read one field of a large shared struct without
+    /// exposing the whole value, e.g. `state.read().map(|s| &s.cart)`.
+    pub fn map<U>(self, f: impl FnOnce(&T) -> &U) -> SharedLockReadGuard<'a, U, Orig> {
+        // SAFETY: `self.guard` keeps the underlying lock (and the data behind it) alive for
+        // `'a`. The projected reference is reborrowed with that same lifetime and carried
+        // alongside the guard that justifies it, so it cannot outlive the lock.
+        let value: &'a U = unsafe { &*(f(self.value) as *const U) };
+        SharedLockReadGuard {
+            guard: self.guard,
+            value,
+        }
+    }
+}
+
+/// A write guard over the shared value, or a projection of one of its fields via [`Self::map_mut`].
+///
+/// `Orig` is the type the lock was originally created with; it stays fixed across `map_mut`
+/// calls so the guard keeps holding the same lock no matter how many times `T` is projected.
+pub struct SharedLockWriteGuard<'a, T, Orig = T> {
+    guard: RwLockWriteGuard<'a, ProvidedStateInner<Orig>>,
+    // A raw pointer rather than a stored `&'a mut T`: `Drop` reborrows `guard` directly to
+    // notify subscribers, and a live `&mut T` into the same allocation sitting in this field
+    // at that point would alias it. Going through a pointer means the only `&mut T` that
+    // exists is the transient one `deref_mut`/`map_mut` hands out, which is gone well before
+    // `Drop` runs.
+    value: NonNull<T>,
+    // Whether subscribers should be notified, with the mutated value, when this guard (or the
+    // outermost guard it was projected from) is dropped. Set from `write`/`try_write`, but not
+    // `write_silent`, mirroring which of those notify consumers.
+    notify_subscribers_on_drop: bool,
 }
 
-impl<'a, T> Deref for SharedLockWriteGuard<'a, T> {
+impl<'a, T, Orig> Deref for SharedLockWriteGuard<'a, T, Orig> {
     type Target = T;
 
     fn deref(&self) -> &Self::Target {
-        &self.guard.value
+        // SAFETY: `value` was derived from data `guard` holds exclusive access to for `'a`,
+        // and no other reference to it is ever held alongside this one.
+        unsafe { self.value.as_ref() }
     }
 }
 
-impl<'a, T> DerefMut for SharedLockWriteGuard<'a, T> {
+impl<'a, T, Orig> DerefMut for SharedLockWriteGuard<'a, T, Orig> {
     fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.guard.value
+        // SAFETY: see `Deref::deref`.
+        unsafe { self.value.as_mut() }
+    }
+}
+
+impl<'a, T, Orig> Drop for SharedLockWriteGuard<'a, T, Orig> {
+    fn drop(&mut self) {
+        // Subscribers fire here, after the caller has had a chance to mutate through the
+        // guard, so they always observe the new value rather than the one from before this
+        // write. The lock is still held at this point - see `UseSharedLock::subscribe`.
+        if self.notify_subscribers_on_drop {
+            self.guard.notify_subscribers();
+        }
+    }
+}
+
+impl<'a, T> SharedLockWriteGuard<'a, T, T> {
+    /// Build a top-level (non-projected) guard from a freshly acquired lock guard.
+    ///
+    /// `notify_subscribers_on_drop` should be `true` for `write`/`try_write`, and `false` for
+    /// `write_silent`.
+    pub(crate) fn from_guard(
+        mut guard: RwLockWriteGuard<'a, ProvidedStateInner<T>>,
+        notify_subscribers_on_drop: bool,
+    ) -> Self {
+        let value = NonNull::from(&mut guard.value);
+        Self {
+            guard,
+            value,
+            notify_subscribers_on_drop,
+        }
+    }
+}
+
+impl<'a, T, Orig> SharedLockWriteGuard<'a, T, Orig> {
+    /// Project this guard onto a sub-field of `T`, keeping the lock held for the guard's lifetime.
+    ///
+    /// This lets a component mutate one field of a large shared struct without exposing the
+    /// whole value, e.g. `state.write().map_mut(|s| &mut s.cart)`.
+    pub fn map_mut<U>(self, f: impl FnOnce(&mut T) -> &mut U) -> SharedLockWriteGuard<'a, U, Orig> {
+        let notify_subscribers_on_drop = self.notify_subscribers_on_drop;
+        let mut value = self.value;
+        // `guard` can't be moved out of `self` directly because `SharedLockWriteGuard` has a
+        // `Drop` impl (E0509). Wrap it so dropping `this` doesn't run that impl - which would
+        // notify subscribers early, for an intermediate projection step instead of once the
+        // final guard is dropped - then read it out exactly once.
+        let this = std::mem::ManuallyDrop::new(self);
+        // SAFETY: `this.guard` is read exactly once and never used through `this` again;
+        // `this` itself is never dropped (its `Drop` is the point).
+        let guard = unsafe { std::ptr::read(&this.guard) };
+        // SAFETY: `value` points into data `guard` holds exclusive access to; this borrow is
+        // dropped before `f`'s result is captured into the new `NonNull`.
+        let orig_value: &mut T = unsafe { value.as_mut() };
+        let value = NonNull::from(f(orig_value));
+        SharedLockWriteGuard {
+            guard,
+            value,
+            notify_subscribers_on_drop,
+        }
     }
 }
 
@@ -87,7 +199,7 @@ impl<T> UseSharedLock<T> {
     /// Read the shared value
     pub fn read(&self) -> SharedLockReadGuard<T> {
         match self.inner.read() {
-            Ok(guard) => SharedLockReadGuard { guard },
+            Ok(guard) => SharedLockReadGuard::from_guard(guard),
             Err(message) => panic!(
                 "Reading the shared state failed: {}\n({:?})",
                 message, message
@@ -100,7 +212,7 @@ impl<T> UseSharedLock<T> {
         match self.inner.write() {
             Ok(mut guard) => {
                 guard.notify_consumers();
-                SharedLockWriteGuard { guard }
+                SharedLockWriteGuard::from_guard(guard, true)
             }
             Err(message) => panic!(
                 "Reading the shared state failed: {}\n({:?})",
@@ -109,10 +221,14 @@ impl<T> UseSharedLock<T> {
         }
     }
 
-    /// Tries writing the value without forcing a re-render
+    /// Write the value without forcing a re-render or notifying subscribers.
+    ///
+    /// This is for mutations the rest of the app should not react to at all - a subscriber
+    /// doing persistence or logging will not see this write. Use [`Self::write`] if subscribers
+    /// should observe the change.
     pub fn write_silent(&self) -> SharedLockWriteGuard<'_, T> {
         match self.inner.write() {
-            Ok(guard) => SharedLockWriteGuard { guard },
+            Ok(guard) => SharedLockWriteGuard::from_guard(guard, false),
             Err(message) => panic!(
                 "Reading the shared state failed: {}\n({:?})",
                 message, message
@@ -120,6 +236,52 @@ impl<T> UseSharedLock<T> {
         }
     }
 
+    /// Read the shared value without blocking, instead of panicking if it is contended or poisoned.
+    #[cfg(not(feature = "loom"))]
+    pub fn try_read(&self) -> Result<SharedLockReadGuard<'_, T>, LockError> {
+        match self.inner.try_read() {
+            Ok(guard) => Ok(SharedLockReadGuard::from_guard(guard)),
+            Err(TryLockError::WouldBlock) => Err(LockError::WouldBlock),
+            Err(TryLockError::Poisoned(_)) => Err(LockError::Poisoned),
+        }
+    }
+
+    /// Write the shared value without blocking, instead of panicking if it is contended or poisoned.
+    ///
+    /// As with [`Self::write`], consumers are only notified if the lock was actually acquired.
+    #[cfg(not(feature = "loom"))]
+    pub fn try_write(&self) -> Result<SharedLockWriteGuard<'_, T>, LockError> {
+        match self.inner.try_write() {
+            Ok(mut guard) => {
+                guard.notify_consumers();
+                Ok(SharedLockWriteGuard::from_guard(guard, true))
+            }
+            Err(TryLockError::WouldBlock) => Err(LockError::WouldBlock),
+            Err(TryLockError::Poisoned(_)) => Err(LockError::Poisoned),
+        }
+    }
+
+    /// Clear the poison flag left behind by a writer that panicked while holding the lock.
+    ///
+    /// The last-written value is untouched, so components can keep reading it once the flag
+    /// is cleared instead of panicking on every subsequent render.
+    #[cfg(not(feature = "loom"))]
+    pub fn clear_poison(&self) {
+        self.inner.clear_poison();
+    }
+
+    /// Register a callback to run whenever the shared value changes, even from scopes that
+    /// never read it and so would not otherwise re-render.
+    ///
+    /// Returning `false` from `subscriber` drops the subscription.
+    ///
+    /// `subscriber` runs while the write lock is still held (right before it's released), so
+    /// it must not call [`Self::write`] or [`Self::write_silent`] on the same `UseSharedLock` -
+    /// doing so will deadlock. [`Self::try_write`] returns [`LockError::WouldBlock`] there.
+    pub fn subscribe(&self, subscriber: impl FnMut(&T) -> bool + Send + Sync + 'static) {
+        self.inner.write().unwrap().subscribers.push(Box::new(subscriber));
+    }
+
     /// Take a reference to the inner value temporarily and produce a new value
     pub fn with<O>(&self, immutable_callback: impl FnOnce(&T) -> O) -> O {
         immutable_callback(&*self.read())
@@ -153,6 +315,70 @@ pub fn use_shared_lock_provider<T: 'static>(cx: &ScopeState, f: impl FnOnce() ->
             notify_any: cx.schedule_update_any(),
             consumers: HashSet::new(),
             gen: 0,
+            subscribers: Vec::new(),
         })));
     });
 }
+
+// These exercise the same `gen`/`consumers`/`notify_consumers` logic `UseSharedLockOwner`
+// relies on, under `loom`'s interleavings, without needing a running `ScopeState`.
+#[cfg(all(test, feature = "loom"))]
+mod loom_tests {
+    use super::*;
+    use loom::thread;
+
+    fn new_inner() -> ProvidedLock<i32> {
+        Arc::new(RwLock::new(ProvidedStateInner {
+            value: 0,
+            notify_any: Arc::new(|_| {}),
+            consumers: HashSet::new(),
+            gen: 0,
+            subscribers: Vec::new(),
+        }))
+    }
+
+    /// Two scopes subscribe, one writer notifies while a reader reads the generation, and a
+    /// consumer unsubscribes concurrently with the notification - this should never panic or
+    /// leave `consumers`/`gen` in an inconsistent state no matter the interleaving.
+    #[test]
+    fn concurrent_notify_read_and_unsubscribe() {
+        loom::model(|| {
+            let inner = new_inner();
+            let scope_a = ScopeId(0);
+            let scope_b = ScopeId(1);
+            inner.write().unwrap().consumers.insert(scope_a);
+            inner.write().unwrap().consumers.insert(scope_b);
+
+            let writer = {
+                let inner = inner.clone();
+                thread::spawn(move || {
+                    inner.write().unwrap().notify_consumers();
+                })
+            };
+
+            let reader = {
+                let inner = inner.clone();
+                thread::spawn(move || {
+                    let _ = inner.read().unwrap().gen;
+                })
+            };
+
+            let unsubscriber = {
+                let inner = inner.clone();
+                thread::spawn(move || {
+                    // Mirrors what `UseSharedLockOwner::drop` does when a component unmounts.
+                    inner.write().unwrap().consumers.remove(&scope_b);
+                })
+            };
+
+            writer.join().unwrap();
+            reader.join().unwrap();
+            unsubscriber.join().unwrap();
+
+            let inner = inner.read().unwrap();
+            assert_eq!(inner.gen, 1);
+            assert!(inner.consumers.contains(&scope_a));
+            assert!(!inner.consumers.contains(&scope_b));
+        });
+    }
+}
@@ -0,0 +1,39 @@
+use dioxus_core::ScopeId;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+/// The state shared behind a [`crate::use_shared_lock::UseSharedLock`], reachable through the
+/// context tree via [`crate::use_shared_lock::use_shared_lock_provider`].
+pub struct ProvidedStateInner<T> {
+    pub(crate) value: T,
+    pub(crate) notify_any: Arc<dyn Fn(ScopeId) + Send + Sync>,
+    pub(crate) consumers: HashSet<ScopeId>,
+    pub(crate) gen: usize,
+    /// Callbacks registered through [`crate::use_shared_lock::UseSharedLock::subscribe`], run
+    /// on every non-silent write regardless of whether the owning scope renders the value.
+    ///
+    /// `+ Sync` (on top of `Send`) is required so `ProvidedStateInner<T>` - and in turn
+    /// [`crate::use_global_shared_state::SharedState<T>`] - can be held in a `static`.
+    pub(crate) subscribers: Vec<Box<dyn FnMut(&T) -> bool + Send + Sync>>,
+}
+
+impl<T> ProvidedStateInner<T> {
+    /// Bump the generation and re-render every consuming scope.
+    ///
+    /// This runs at write-guard acquisition, before the caller has mutated the value through
+    /// the guard - subscribers are notified separately, via [`Self::notify_subscribers`], once
+    /// the mutation has actually happened.
+    pub(crate) fn notify_consumers(&mut self) {
+        self.gen += 1;
+        for consumer in self.consumers.iter() {
+            (self.notify_any)(*consumer);
+        }
+    }
+
+    /// Run every subscriber callback with the current (post-mutation) value, dropping any that
+    /// return `false`. Called when a write guard that acquired the lock via `write`/`try_write`
+    /// is dropped, so subscribers always observe the new value.
+    pub(crate) fn notify_subscribers(&mut self) {
+        self.subscribers.retain_mut(|subscriber| subscriber(&self.value));
+    }
+}